@@ -0,0 +1,218 @@
+//! Animated GIF to animated WebP conversion.
+//!
+//! Decodes every frame of a GIF (with its delay) through `image`'s
+//! `GifDecoder`, then drives libwebp's animation encoder directly via
+//! `libwebp-sys` so the output stays animated instead of collapsing to a
+//! single still.
+
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, DynamicImage};
+use libwebp_sys::{
+    WebPAnimEncoderAdd, WebPAnimEncoderAssemble, WebPAnimEncoderDelete,
+    WebPAnimEncoderNewInternal, WebPAnimEncoderOptions, WebPAnimEncoderOptionsInitInternal,
+    WebPConfig, WebPData, WebPDataClear, WebPMuxAnimParams, WebPMuxAssemble, WebPMuxCreateInternal,
+    WebPMuxDelete, WebPMuxError, WebPMuxSetAnimationParams, WebPPicture, WebPPictureFree,
+    WebPPictureImportRGBA, WEBP_MUX_ABI_VERSION,
+};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// True when `path` is a GIF containing more than one frame.
+pub fn is_animated_gif(path: &Path) -> bool {
+    let ext_is_gif = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("gif"))
+        .unwrap_or(false);
+
+    if !ext_is_gif {
+        return false;
+    }
+
+    frame_count_at_least_two(path).unwrap_or(false)
+}
+
+fn frame_count_at_least_two(path: &Path) -> Result<bool, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let decoder = GifDecoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut frames = decoder.into_frames();
+    Ok(frames.next().is_some() && frames.next().is_some())
+}
+
+/// Decode every frame of the GIF at `path` and encode them as a single
+/// animated WebP at `quality`, preserving per-frame delay. The output always
+/// loops forever; the GIF's own loop count is not read.
+///
+/// This drives `libwebp-sys` directly rather than the `webp` crate's
+/// `AnimEncoder::try_encode()`: that wrapper always terminates the encoder
+/// with a hardcoded `WebPAnimEncoderAdd(enc, null, 0, ...)`, which discards
+/// the last frame's real duration. Finishing with the true accumulated
+/// `timestamp_ms` instead preserves it.
+pub fn convert_animated_gif(path: &Path, quality: f32) -> Result<Vec<u8>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open GIF: {}", e))?;
+    let decoder =
+        GifDecoder::new(BufReader::new(file)).map_err(|e| format!("Failed to read GIF: {}", e))?;
+    let gif_frames = decoder
+        .into_frames()
+        .collect_frames()
+        .map_err(|e| format!("Failed to decode GIF frames: {}", e))?;
+
+    if gif_frames.is_empty() {
+        return Err("GIF has no frames".to_string());
+    }
+
+    let width = gif_frames[0].buffer().width();
+    let height = gif_frames[0].buffer().height();
+
+    let mut config = WebPConfig::new().map_err(|_| "Failed to create WebP config".to_string())?;
+    config.quality = quality;
+
+    let rgba_frames: Vec<_> = gif_frames
+        .iter()
+        .map(|frame| DynamicImage::ImageRgba8(frame.buffer().clone()).to_rgba8())
+        .collect();
+    let delays_ms: Vec<i32> = gif_frames
+        .iter()
+        .map(|frame| frame.delay().numer_denom_ms().0.max(1) as i32)
+        .collect();
+
+    unsafe {
+        let mux_abi_version = WEBP_MUX_ABI_VERSION as i32;
+        let mut options = std::mem::MaybeUninit::<WebPAnimEncoderOptions>::uninit();
+        if WebPAnimEncoderOptionsInitInternal(options.as_mut_ptr(), mux_abi_version) == 0 {
+            return Err("Failed to init WebP animation encoder options".to_string());
+        }
+        let mut options = options.assume_init();
+        // `image`'s GifDecoder doesn't expose the GIF's NETSCAPE loop count, so
+        // we can't preserve a finite repeat count here; 0 (loop forever) is
+        // libwebp's default and the closest available approximation.
+        options.anim_params = WebPMuxAnimParams {
+            bgcolor: 0,
+            loop_count: 0,
+        };
+
+        let encoder = WebPAnimEncoderNewInternal(width as i32, height as i32, &options, mux_abi_version);
+        if encoder.is_null() {
+            return Err("Failed to create WebP animation encoder".to_string());
+        }
+
+        let mut timestamp_ms = 0i32;
+        for (rgba, delay_ms) in rgba_frames.iter().zip(&delays_ms) {
+            let mut pic = match WebPPicture::new() {
+                Ok(pic) => pic,
+                Err(_) => {
+                    WebPAnimEncoderDelete(encoder);
+                    return Err("Failed to init WebP picture".to_string());
+                }
+            };
+            pic.width = width as i32;
+            pic.height = height as i32;
+            pic.use_argb = 1;
+
+            if WebPPictureImportRGBA(&mut pic, rgba.as_ptr(), width as i32 * 4) == 0 {
+                WebPPictureFree(&mut pic);
+                WebPAnimEncoderDelete(encoder);
+                return Err("Failed to import RGBA frame into WebP picture".to_string());
+            }
+
+            let added = WebPAnimEncoderAdd(encoder, &mut pic, timestamp_ms, &config);
+            let error_code = pic.error_code;
+            WebPPictureFree(&mut pic);
+            if added == 0 {
+                WebPAnimEncoderDelete(encoder);
+                return Err(format!("Failed to add animation frame: {:?}", error_code));
+            }
+
+            timestamp_ms += delay_ms;
+        }
+
+        // Flush with the real total duration so the last frame keeps its
+        // actual delay instead of being clipped to zero.
+        if WebPAnimEncoderAdd(encoder, std::ptr::null_mut(), timestamp_ms, std::ptr::null()) == 0 {
+            WebPAnimEncoderDelete(encoder);
+            return Err("Failed to finalize animated WebP".to_string());
+        }
+
+        let mut webp_data = std::mem::MaybeUninit::<WebPData>::uninit();
+        let assembled = WebPAnimEncoderAssemble(encoder, webp_data.as_mut_ptr());
+        WebPAnimEncoderDelete(encoder);
+        if assembled == 0 {
+            return Err("Failed to assemble animated WebP".to_string());
+        }
+        let mut webp_data = webp_data.assume_init();
+
+        let mux = WebPMuxCreateInternal(&webp_data, 1, mux_abi_version);
+        WebPDataClear(&mut webp_data);
+        if mux.is_null() {
+            return Err("Failed to create WebP mux for animation params".to_string());
+        }
+
+        let anim_params = WebPMuxAnimParams {
+            bgcolor: 0,
+            loop_count: 0,
+        };
+        let mux_result = WebPMuxSetAnimationParams(mux, &anim_params);
+        if mux_result != WebPMuxError::WEBP_MUX_OK {
+            WebPMuxDelete(mux);
+            return Err(format!("Failed to set animation params: {:?}", mux_result));
+        }
+
+        let mut assembled_data = std::mem::MaybeUninit::<WebPData>::uninit();
+        let assemble_result = WebPMuxAssemble(mux, assembled_data.as_mut_ptr());
+        WebPMuxDelete(mux);
+        if assemble_result != WebPMuxError::WEBP_MUX_OK {
+            return Err(format!("Failed to assemble WebP mux: {:?}", assemble_result));
+        }
+
+        let mut assembled_data = assembled_data.assume_init();
+        let result = std::slice::from_raw_parts(assembled_data.bytes, assembled_data.size).to_vec();
+        // `assembled_data.bytes` was heap-allocated by libwebp's own
+        // allocator; it must be freed through libwebp, not left for Rust
+        // to leak.
+        WebPDataClear(&mut assembled_data);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, ImageBuffer, Rgba};
+
+    fn write_gif(name: &str, frame_count: usize) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("anim-test-{}-{}", std::process::id(), name));
+        let file = File::create(&path).unwrap();
+        let mut encoder = GifEncoder::new(file);
+        let frames = (0..frame_count).map(|i| {
+            let buf = ImageBuffer::<Rgba<u8>, _>::from_pixel(2, 2, Rgba([i as u8, 0, 0, 255]));
+            Frame::from_parts(buf, 0, 0, Delay::from_numer_denom_ms(100, 1))
+        });
+        encoder.encode_frames(frames).unwrap();
+        path
+    }
+
+    #[test]
+    fn is_animated_gif_false_for_single_frame() {
+        let path = write_gif("single.gif", 1);
+        assert!(!is_animated_gif(&path));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn is_animated_gif_true_for_multi_frame() {
+        let path = write_gif("multi.gif", 2);
+        assert!(is_animated_gif(&path));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn is_animated_gif_false_for_non_gif_extension() {
+        let gif_path = write_gif("renamed.gif", 2);
+        let png_path = gif_path.with_extension("png");
+        std::fs::rename(&gif_path, &png_path).unwrap();
+        assert!(!is_animated_gif(&png_path));
+        std::fs::remove_file(png_path).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+//! Input decoding for formats beyond what the `image` crate handles natively.
+//!
+//! HEIF/HEIC and camera RAW formats route through dedicated decoder
+//! libraries and land as a plain `DynamicImage`, so the rest of the
+//! pipeline (metadata handling, WebP encoding) never has to know the
+//! source format. Each decoder sits behind its own cargo feature, so a
+//! build without the system `libheif`/libraw still compiles and handles
+//! every format `image` already supports natively.
+
+use image::DynamicImage;
+use std::path::Path;
+
+#[cfg(feature = "heif")]
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+#[cfg(not(feature = "heif"))]
+const HEIF_EXTENSIONS: &[&str] = &[];
+
+#[cfg(feature = "raw")]
+const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw", "dng", "rw2"];
+#[cfg(not(feature = "raw"))]
+const RAW_EXTENSIONS: &[&str] = &[];
+
+/// Extensions this module can decode, in addition to whatever `image::open`
+/// already supports natively.
+pub fn extra_extensions() -> Vec<&'static str> {
+    HEIF_EXTENSIONS
+        .iter()
+        .chain(RAW_EXTENSIONS.iter())
+        .copied()
+        .collect()
+}
+
+/// Load `path` into a `DynamicImage`, dispatching to a specialized decoder
+/// when the extension requires one, falling back to `image::open` otherwise.
+pub fn load_image(path: &Path) -> Result<DynamicImage, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    if HEIF_EXTENSIONS.contains(&ext.as_str()) {
+        return decode_heif(path);
+    }
+
+    if RAW_EXTENSIONS.contains(&ext.as_str()) {
+        return decode_raw(path);
+    }
+
+    image::open(path).map_err(|e| format!("Failed to load image: {}", e))
+}
+
+#[cfg(feature = "heif")]
+fn decode_heif(path: &Path) -> Result<DynamicImage, String> {
+    use image::{ImageBuffer, Rgb, Rgba};
+    use libheif_rs::{ColorSpace, HeifContext, ItemId, LibHeif, RgbChroma};
+
+    let lib_heif = LibHeif::new();
+    let ctx = HeifContext::read_from_file(path.to_string_lossy().as_ref())
+        .map_err(|e| format!("Failed to open HEIF file: {}", e))?;
+    let handle = ctx
+        .primary_image_handle()
+        .map_err(|e| format!("Failed to read primary HEIF image: {}", e))?;
+
+    let has_alpha = handle.has_alpha_channel();
+    let chroma = if has_alpha {
+        RgbChroma::Rgba
+    } else {
+        RgbChroma::Rgb
+    };
+
+    let image = lib_heif
+        .decode(&handle, ColorSpace::Rgb(chroma), None)
+        .map_err(|e| format!("Failed to decode HEIF image: {}", e))?;
+
+    let width = image.width();
+    let height = image.height();
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| "HEIF image has no interleaved RGB(A) plane".to_string())?;
+
+    // libheif rows may be padded to `stride`; copy row-by-row into a tightly
+    // packed buffer before handing it to `image`.
+    let channels: usize = if has_alpha { 4 } else { 3 };
+    let mut packed = Vec::with_capacity(width as usize * height as usize * channels);
+    for row in 0..height as usize {
+        let start = row * plane.stride;
+        let end = start + width as usize * channels;
+        packed.extend_from_slice(&plane.data[start..end]);
+    }
+
+    if has_alpha {
+        ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, packed)
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| "HEIF buffer dimensions do not match decoded data".to_string())
+    } else {
+        ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, packed)
+            .map(DynamicImage::ImageRgb8)
+            .ok_or_else(|| "HEIF buffer dimensions do not match decoded data".to_string())
+    }
+}
+
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_path: &Path) -> Result<DynamicImage, String> {
+    Err("HEIC/HEIF input requires the \"heif\" build feature".to_string())
+}
+
+#[cfg(feature = "raw")]
+fn decode_raw(path: &Path) -> Result<DynamicImage, String> {
+    use image::{ImageBuffer, Rgb};
+    use imagepipe::{ImageSource, Pipeline};
+
+    let raw_image = rawloader::decode_file(path).map_err(|e| format!("Failed to decode RAW file: {}", e))?;
+
+    let mut pipeline = Pipeline::new_from_source(ImageSource::Raw(raw_image))
+        .map_err(|e| format!("Failed to build RAW pipeline: {}", e))?;
+    pipeline.run(None);
+
+    let decoded = pipeline
+        .output_8bit(None)
+        .map_err(|e| format!("Failed to demosaic RAW file: {}", e))?;
+
+    ImageBuffer::<Rgb<u8>, _>::from_raw(decoded.width as u32, decoded.height as u32, decoded.data)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "RAW pipeline output dimensions do not match decoded data".to_string())
+}
+
+#[cfg(not(feature = "raw"))]
+fn decode_raw(_path: &Path) -> Result<DynamicImage, String> {
+    Err("Camera RAW input requires the \"raw\" build feature".to_string())
+}
@@ -0,0 +1,130 @@
+//! File selection filters for the input scan.
+//!
+//! Adds include/exclude extension lists, glob-matched excluded directory
+//! segments, and byte-size bounds, all applied while `get_image_files`
+//! walks the input directory.
+
+use glob::Pattern;
+use std::path::Path;
+
+pub struct FileFilter {
+    include_ext: Option<Vec<String>>,
+    exclude_ext: Vec<String>,
+    exclude_dir: Vec<Pattern>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+impl FileFilter {
+    pub fn new(
+        include_ext: Option<&str>,
+        exclude_ext: Option<&str>,
+        exclude_dir: Option<&str>,
+        min_size: Option<u64>,
+        max_size: Option<u64>,
+    ) -> Result<FileFilter, String> {
+        let exclude_dir = exclude_dir
+            .map(|csv| {
+                csv.split(',')
+                    .map(|p| Pattern::new(p.trim()).map_err(|e| format!("Invalid --exclude-dir glob {:?}: {}", p, e)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(FileFilter {
+            include_ext: include_ext.map(split_exts),
+            exclude_ext: exclude_ext.map(split_exts).unwrap_or_default(),
+            exclude_dir,
+            min_size,
+            max_size,
+        })
+    }
+
+    /// True when the directory named `dir_name` (a single path segment)
+    /// matches an `--exclude-dir` glob. Checked separately from
+    /// file-level filters so `WalkDir` can prune whole subtrees.
+    pub fn excludes_dir(&self, dir_name: &str) -> bool {
+        self.exclude_dir.iter().any(|p| p.matches(dir_name))
+    }
+
+    /// True when the file at `path` with `size` bytes passes every filter.
+    pub fn accepts(&self, path: &Path, size: u64) -> bool {
+        let ext = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) => e.to_lowercase(),
+            None => return false,
+        };
+
+        if let Some(include) = &self.include_ext {
+            if !include.contains(&ext) {
+                return false;
+            }
+        }
+
+        if self.exclude_ext.contains(&ext) {
+            return false;
+        }
+
+        if let Some(min) = self.min_size {
+            if size < min {
+                return false;
+            }
+        }
+
+        if let Some(max) = self.max_size {
+            if size > max {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+fn split_exts(csv: &str) -> Vec<String> {
+    csv.split(',')
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_respects_include_ext() {
+        let filter = FileFilter::new(Some("png,jpg"), None, None, None, None).unwrap();
+        assert!(filter.accepts(Path::new("photo.png"), 100));
+        assert!(!filter.accepts(Path::new("photo.gif"), 100));
+    }
+
+    #[test]
+    fn accepts_respects_exclude_ext() {
+        let filter = FileFilter::new(None, Some("gif,bmp"), None, None, None).unwrap();
+        assert!(!filter.accepts(Path::new("anim.gif"), 100));
+        assert!(filter.accepts(Path::new("photo.png"), 100));
+    }
+
+    #[test]
+    fn accepts_respects_size_bounds() {
+        let filter = FileFilter::new(None, None, None, Some(100), Some(1000)).unwrap();
+        assert!(!filter.accepts(Path::new("tiny.png"), 50));
+        assert!(filter.accepts(Path::new("ok.png"), 500));
+        assert!(!filter.accepts(Path::new("huge.png"), 5000));
+    }
+
+    #[test]
+    fn accepts_rejects_extensionless_paths() {
+        let filter = FileFilter::new(None, None, None, None, None).unwrap();
+        assert!(!filter.accepts(Path::new("no_extension"), 100));
+    }
+
+    #[test]
+    fn excludes_dir_matches_glob() {
+        let filter = FileFilter::new(None, None, Some("node_modules,.git"), None, None).unwrap();
+        assert!(filter.excludes_dir("node_modules"));
+        assert!(filter.excludes_dir(".git"));
+        assert!(!filter.excludes_dir("src"));
+    }
+}
@@ -1,15 +1,37 @@
 use chrono::Local;
 use clap::Parser;
-use image::DynamicImage;
 use rayon::prelude::*;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tracing::{error, info, info_span};
+use tracing_subscriber::EnvFilter;
 use walkdir::WalkDir;
 
-const SUPPORTED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif"];
+mod anim;
+mod decode;
+mod filter;
+mod manifest;
+mod metadata;
+mod report;
+
+use filter::FileFilter;
+use manifest::Manifest;
+use metadata::MetadataMode;
+use report::{ConversionReport, Report, Summary};
+
+const BASE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif"];
+
+/// All extensions this build can read: the formats `image` decodes natively
+/// plus whatever `decode` pulls in via its cargo features.
+fn supported_extensions() -> Vec<&'static str> {
+    let mut exts = BASE_EXTENSIONS.to_vec();
+    exts.extend(decode::extra_extensions());
+    exts
+}
 
 #[derive(Parser)]
 #[command(name = "image-converter")]
@@ -30,6 +52,46 @@ struct Args {
     /// Number of parallel workers (default: CPU cores)
     #[arg(short, long, default_value_t = 0)]
     workers: usize,
+
+    /// How much source metadata to carry into the output
+    #[arg(long, value_enum, default_value_t = MetadataMode::Orientation)]
+    metadata: MetadataMode,
+
+    /// Ignore the conversion manifest and reconvert every file
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    /// Only convert files with these comma-separated extensions (e.g. "jpg,png")
+    #[arg(long)]
+    include_ext: Option<String>,
+
+    /// Never convert files with these comma-separated extensions
+    #[arg(long)]
+    exclude_ext: Option<String>,
+
+    /// Skip directories whose name matches this comma-separated list of globs (e.g. ".git,node_modules")
+    #[arg(long)]
+    exclude_dir: Option<String>,
+
+    /// Skip files smaller than this many bytes
+    #[arg(long)]
+    min_size: Option<u64>,
+
+    /// Skip files larger than this many bytes
+    #[arg(long)]
+    max_size: Option<u64>,
+
+    /// Stack size in bytes for each rayon worker thread (default: Rust's default)
+    #[arg(long)]
+    thread_stack_size: Option<usize>,
+
+    /// Log verbosity (error, warn, info, debug, trace); overridden by RUST_LOG
+    #[arg(long, default_value = "info")]
+    log_level: String,
+
+    /// Write a machine-readable JSON report of the run to this path
+    #[arg(long)]
+    report: Option<String>,
 }
 
 struct ConversionResult {
@@ -40,11 +102,18 @@ struct ConversionResult {
     original_size: u64,
     new_size: u64,
     savings: f64,
+    duration: Duration,
 }
 
 fn main() {
     let args = Args::parse();
 
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&args.log_level)),
+        )
+        .init();
+
     let quality = args.quality.clamp(1, 100);
     let workers = if args.workers > 0 {
         args.workers
@@ -57,10 +126,26 @@ fn main() {
     let output_dir = PathBuf::from(&args.output);
 
     // Configure rayon thread pool
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(workers)
-        .build_global()
-        .unwrap_or(());
+    let mut pool_builder = rayon::ThreadPoolBuilder::new().num_threads(workers);
+    if let Some(stack_size) = args.thread_stack_size {
+        // Large RAW/HEIF decodes can overflow the default worker stack.
+        pool_builder = pool_builder.stack_size(stack_size);
+    }
+    pool_builder.build_global().unwrap_or(());
+
+    let file_filter = match FileFilter::new(
+        args.include_ext.as_deref(),
+        args.exclude_ext.as_deref(),
+        args.exclude_dir.as_deref(),
+        args.min_size,
+        args.max_size,
+    ) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let start = Instant::now();
     let start_time = Local::now().format("%Y-%m-%d %H:%M:%S %Z").to_string();
@@ -77,6 +162,7 @@ fn main() {
     println!("  • Output directory: {}", output_dir.display());
     println!("  • Quality:          {}%", quality);
     println!("  • Workers:          {}", workers);
+    println!("  • Metadata:         {:?}", args.metadata);
     println!();
 
     // Validate input directory
@@ -92,19 +178,25 @@ fn main() {
     }
 
     // Scan for image files
-    let all_files = get_image_files(&input_dir);
+    let all_files = get_image_files(&input_dir, &file_filter);
     let total_found = all_files.len();
 
     if total_found == 0 {
         println!("⚠ No images found in {}", input_dir.display());
-        println!("  Supported formats: {}", SUPPORTED_EXTENSIONS.join(", "));
+        println!("  Supported formats: {}", supported_extensions().join(", "));
         return;
     }
 
     println!("Found {} image(s)", total_found);
 
-    // Filter already converted
-    let (files, skipped) = filter_already_converted(&all_files, &input_dir, &output_dir);
+    // Filter already converted, using the content-hash manifest unless
+    // --force says to ignore it and reconvert everything.
+    let manifest = Mutex::new(if args.force {
+        Manifest::default()
+    } else {
+        Manifest::load(&output_dir)
+    });
+    let (files, skipped) = filter_already_converted(&all_files, &input_dir, &output_dir, &manifest);
     let total_files = files.len();
 
     if skipped > 0 {
@@ -121,48 +213,85 @@ fn main() {
 
     // Parallel conversion
     let counter = AtomicUsize::new(0);
-    let total_original = AtomicU64::new(0);
-    let total_new = AtomicU64::new(0);
-    let successful = AtomicUsize::new(0);
-    let failed = AtomicUsize::new(0);
-
-    files.par_iter().for_each(|file| {
-        let result = convert_image(file, &input_dir, &output_dir, quality as f32);
-        let current = counter.fetch_add(1, Ordering::Relaxed) + 1;
-        let filename = Path::new(&result.input)
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy();
-
-        if result.success {
-            successful.fetch_add(1, Ordering::Relaxed);
-            total_original.fetch_add(result.original_size, Ordering::Relaxed);
-            total_new.fetch_add(result.new_size, Ordering::Relaxed);
-            println!(
-                "[{}/{}] ✓ {}: {} → {} ({:.2}% saved)",
-                current,
-                total_files,
-                filename,
-                format_bytes(result.original_size),
-                format_bytes(result.new_size),
-                result.savings
-            );
-        } else {
-            failed.fetch_add(1, Ordering::Relaxed);
-            println!(
-                "[{}/{}] ✗ {}: {}",
-                current, total_files, filename, result.message
+
+    let results: Vec<ConversionResult> = files
+        .par_iter()
+        .map(|file| {
+            let result = convert_image(file, &input_dir, &output_dir, quality as f32, args.metadata);
+            let current = counter.fetch_add(1, Ordering::Relaxed) + 1;
+            let filename = Path::new(&result.input)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            let span = info_span!(
+                "convert",
+                input = %result.input,
+                output = %result.output,
+                bytes_in = result.original_size,
+                bytes_out = result.new_size,
+                savings_percent = result.savings,
+                duration_ms = result.duration.as_millis() as u64,
             );
-        }
-    });
+            let _enter = span.enter();
+
+            if result.success {
+                let key = manifest_key(file, &input_dir);
+                manifest.lock().unwrap_or_else(|e| e.into_inner()).record(file, &key);
+                info!(
+                    "[{}/{}] {} -> {} ({:.2}% saved)",
+                    current,
+                    total_files,
+                    format_bytes(result.original_size),
+                    format_bytes(result.new_size),
+                    result.savings
+                );
+                println!(
+                    "[{}/{}] ✓ {}: {} → {} ({:.2}% saved)",
+                    current,
+                    total_files,
+                    filename,
+                    format_bytes(result.original_size),
+                    format_bytes(result.new_size),
+                    result.savings
+                );
+            } else {
+                error!("[{}/{}] {}", current, total_files, result.message);
+                println!(
+                    "[{}/{}] ✗ {}: {}",
+                    current, total_files, filename, result.message
+                );
+            }
+
+            result
+        })
+        .collect();
+
+    if let Err(e) = manifest
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .save(&output_dir)
+    {
+        eprintln!("Warning: failed to write conversion manifest: {}", e);
+    }
 
-    let successful = successful.load(Ordering::Relaxed);
-    let failed = failed.load(Ordering::Relaxed);
-    let total_original = total_original.load(Ordering::Relaxed);
-    let total_new = total_new.load(Ordering::Relaxed);
+    let successful = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - successful;
+    let total_original: u64 = results
+        .iter()
+        .filter(|r| r.success)
+        .map(|r| r.original_size)
+        .sum();
+    let total_new: u64 = results.iter().filter(|r| r.success).map(|r| r.new_size).sum();
 
     let end_time = Local::now().format("%Y-%m-%d %H:%M:%S %Z").to_string();
     let elapsed = start.elapsed();
+    let total_savings = if total_original > 0 {
+        ((1.0 - total_new as f64 / total_original as f64) * 10000.0).round() / 100.0
+    } else {
+        0.0
+    };
 
     println!();
     println!("{}", "=".repeat(50));
@@ -173,11 +302,6 @@ fn main() {
     println!("  • Failed:           {}", failed);
 
     if successful > 0 {
-        let total_savings = if total_original > 0 {
-            ((1.0 - total_new as f64 / total_original as f64) * 10000.0).round() / 100.0
-        } else {
-            0.0
-        };
         println!("  • Original size:    {}", format_bytes(total_original));
         println!("  • New size:         {}", format_bytes(total_new));
         println!("  • Total savings:    {:.2}%", total_savings);
@@ -189,18 +313,66 @@ fn main() {
     println!();
     println!("Output directory: {}", output_dir.display());
     println!();
+
+    if let Some(report_path) = &args.report {
+        let report = Report {
+            summary: Summary {
+                total_files,
+                successful,
+                failed,
+                original_size: total_original,
+                new_size: total_new,
+                total_savings_percent: total_savings,
+                elapsed_ms: elapsed.as_millis() as u64,
+            },
+            conversions: results
+                .iter()
+                .map(|r| ConversionReport {
+                    input: r.input.clone(),
+                    output: r.output.clone(),
+                    success: r.success,
+                    message: r.message.clone(),
+                    original_size: r.original_size,
+                    new_size: r.new_size,
+                    savings_percent: r.savings,
+                    duration_ms: r.duration.as_millis() as u64,
+                })
+                .collect(),
+        };
+
+        if let Err(e) = report::write_report(Path::new(report_path), &report) {
+            eprintln!("Warning: failed to write report: {}", e);
+        }
+    }
 }
 
-fn get_image_files(input_dir: &Path) -> Vec<PathBuf> {
+fn get_image_files(input_dir: &Path, file_filter: &FileFilter) -> Vec<PathBuf> {
+    let extensions = supported_extensions();
     WalkDir::new(input_dir)
         .into_iter()
+        .filter_entry(|e| {
+            // Prune excluded directories before WalkDir descends into them.
+            if e.file_type().is_dir() {
+                e.file_name()
+                    .to_str()
+                    .map(|name| !file_filter.excludes_dir(name))
+                    .unwrap_or(true)
+            } else {
+                true
+            }
+        })
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
         .filter(|e| {
             e.path()
                 .extension()
                 .and_then(|ext| ext.to_str())
-                .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .map(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter(|e| {
+            e.metadata()
+                .map(|m| file_filter.accepts(e.path(), m.len()))
                 .unwrap_or(false)
         })
         .map(|e| e.into_path())
@@ -214,17 +386,29 @@ fn get_output_path(input_path: &Path, input_dir: &Path, output_dir: &Path) -> Pa
     out
 }
 
+/// Manifest key for a source file: its path relative to the input directory.
+fn manifest_key(input_path: &Path, input_dir: &Path) -> String {
+    input_path
+        .strip_prefix(input_dir)
+        .unwrap_or(input_path)
+        .to_string_lossy()
+        .into_owned()
+}
+
 fn filter_already_converted(
     files: &[PathBuf],
     input_dir: &Path,
     output_dir: &Path,
+    manifest: &Mutex<Manifest>,
 ) -> (Vec<PathBuf>, usize) {
     let mut to_convert = Vec::with_capacity(files.len());
     let mut skipped = 0;
 
+    let manifest = manifest.lock().unwrap_or_else(|e| e.into_inner());
     for file in files {
         let output_path = get_output_path(file, input_dir, output_dir);
-        if output_path.exists() {
+        let key = manifest_key(file, input_dir);
+        if output_path.exists() && manifest.is_unchanged(file, &key) {
             skipped += 1;
         } else {
             to_convert.push(file.clone());
@@ -239,7 +423,9 @@ fn convert_image(
     input_dir: &Path,
     output_dir: &Path,
     quality: f32,
+    metadata_mode: MetadataMode,
 ) -> ConversionResult {
+    let started = Instant::now();
     let input_str = input_path.display().to_string();
     let output_path = get_output_path(input_path, input_dir, output_dir);
     let output_str = output_path.display().to_string();
@@ -255,6 +441,7 @@ fn convert_image(
                 original_size: 0,
                 new_size: 0,
                 savings: 0.0,
+                duration: started.elapsed(),
             };
         }
     }
@@ -271,46 +458,100 @@ fn convert_image(
                 original_size: 0,
                 new_size: 0,
                 savings: 0.0,
+                duration: started.elapsed(),
             };
         }
     };
 
-    // Load image
-    let img: DynamicImage = match image::open(input_path) {
-        Ok(img) => img,
-        Err(e) => {
-            return ConversionResult {
-                input: input_str,
-                output: output_str,
-                success: false,
-                message: format!("Failed to load image: {}", e),
-                original_size,
-                new_size: 0,
-                savings: 0.0,
-            };
+    // Multi-frame GIFs get their own path so the animation survives the
+    // conversion instead of being flattened to its first frame.
+    let webp_data: Vec<u8> = if anim::is_animated_gif(input_path) {
+        match anim::convert_animated_gif(input_path, quality) {
+            Ok(data) => data,
+            Err(message) => {
+                return ConversionResult {
+                    input: input_str,
+                    output: output_str,
+                    success: false,
+                    message,
+                    original_size,
+                    new_size: 0,
+                    savings: 0.0,
+                    duration: started.elapsed(),
+                };
+            }
         }
-    };
+    } else {
+        let img = match decode::load_image(input_path) {
+            Ok(img) => img,
+            Err(message) => {
+                return ConversionResult {
+                    input: input_str,
+                    output: output_str,
+                    success: false,
+                    message,
+                    original_size,
+                    new_size: 0,
+                    savings: 0.0,
+                    duration: started.elapsed(),
+                };
+            }
+        };
 
-    // Encode to WebP using the webp crate (native libwebp bindings)
-    let encoder = match webp::Encoder::from_image(&img) {
-        Ok(enc) => enc,
-        Err(e) => {
-            return ConversionResult {
-                input: input_str,
-                output: output_str,
-                success: false,
-                message: format!("Failed to create encoder: {}", e),
-                original_size,
-                new_size: 0,
-                savings: 0.0,
-            };
+        // `strip` never uses the EXIF/ICC data, so skip the file re-read and
+        // parse entirely instead of reading metadata no one will consume.
+        let source_meta = (metadata_mode != MetadataMode::Strip)
+            .then(|| metadata::read_source_metadata(input_path));
+        let img = match &source_meta {
+            Some(meta) => metadata::apply_orientation(img, meta.orientation),
+            None => img,
+        };
+
+        // Encode to WebP using the webp crate (native libwebp bindings)
+        let encoder = match webp::Encoder::from_image(&img) {
+            Ok(enc) => enc,
+            Err(e) => {
+                return ConversionResult {
+                    input: input_str,
+                    output: output_str,
+                    success: false,
+                    message: format!("Failed to create encoder: {}", e),
+                    original_size,
+                    new_size: 0,
+                    savings: 0.0,
+                    duration: started.elapsed(),
+                };
+            }
+        };
+
+        let encoded = encoder.encode(quality).to_vec();
+
+        if metadata_mode == MetadataMode::All {
+            let meta = source_meta
+                .as_ref()
+                .expect("metadata mode All always reads source metadata above");
+            match metadata::mux_metadata(encoded, meta) {
+                Ok(data) => data,
+                Err(e) => {
+                    return ConversionResult {
+                        input: input_str,
+                        output: output_str,
+                        success: false,
+                        message: e,
+                        original_size,
+                        new_size: 0,
+                        savings: 0.0,
+                        duration: started.elapsed(),
+                    };
+                }
+            }
+        } else {
+            encoded
         }
     };
 
-    let webp_data = encoder.encode(quality);
-
     // Write output
-    if let Err(e) = fs::write(&output_path, &*webp_data) {
+    if let Err(e) = fs::write(&output_path, &webp_data) {
         return ConversionResult {
             input: input_str,
             output: output_str,
@@ -319,6 +560,7 @@ fn convert_image(
             original_size,
             new_size: 0,
             savings: 0.0,
+            duration: started.elapsed(),
         };
     }
 
@@ -337,6 +579,7 @@ fn convert_image(
         original_size,
         new_size,
         savings,
+        duration: started.elapsed(),
     }
 }
 
@@ -0,0 +1,170 @@
+//! Sidecar manifest for detecting real source changes.
+//!
+//! Keeps a JSON file in the output directory mapping each source path to a
+//! content hash plus mtime/size, so a source can be skipped only when its
+//! content actually matches what was last converted. When checking a
+//! source, the file is only hashed in full once its cheap partial hash and
+//! size already match the recorded entry; recording a converted source
+//! always hashes it in full.
+
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const MANIFEST_FILE: &str = ".webp-manifest.json";
+const PARTIAL_HASH_BYTES: usize = 4096;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SourceRecord {
+    size: u64,
+    mtime: u64,
+    partial_hash: u128,
+    full_hash: u128,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    #[serde(flatten)]
+    entries: HashMap<String, SourceRecord>,
+}
+
+impl Manifest {
+    pub fn load(output_dir: &Path) -> Manifest {
+        let path = manifest_path(output_dir);
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, output_dir: &Path) -> Result<(), String> {
+        let path = manifest_path(output_dir);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    /// True when `source`'s current content hash matches what's recorded,
+    /// meaning it doesn't need to be reconverted. The full file is only
+    /// hashed when the cheap partial hash and size already collide with the
+    /// recorded entry.
+    pub fn is_unchanged(&self, source: &Path, key: &str) -> bool {
+        let Some(record) = self.entries.get(key) else {
+            return false;
+        };
+
+        let Ok(partial) = partial_hash(source) else {
+            return false;
+        };
+
+        if record.size != partial.0 || record.partial_hash != partial.1 {
+            return false;
+        }
+
+        let Ok(full_hash) = full_hash(source) else {
+            return false;
+        };
+
+        record.full_hash == full_hash
+    }
+
+    pub fn record(&mut self, source: &Path, key: &str) {
+        let (Ok((size, partial_hash)), Ok(full_hash)) = (partial_hash(source), full_hash(source))
+        else {
+            return;
+        };
+
+        let mtime = fs::metadata(source)
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.entries.insert(
+            key.to_string(),
+            SourceRecord {
+                size,
+                mtime,
+                partial_hash,
+                full_hash,
+            },
+        );
+    }
+}
+
+fn manifest_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(MANIFEST_FILE)
+}
+
+/// Cheap candidate hash: file size plus a SipHash-128 of the leading 4KiB.
+fn partial_hash(path: &Path) -> Result<(u64, u128), String> {
+    let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+
+    let mut head = vec![0u8; PARTIAL_HASH_BYTES.min(size as usize)];
+    file.read_exact(&mut head).map_err(|e| e.to_string())?;
+
+    Ok((size, sip_hash(&head)))
+}
+
+/// Full-file SipHash-128, only worth computing once the partial hash and
+/// size already agree with a candidate.
+fn full_hash(path: &Path) -> Result<u128, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+    Ok(sip_hash(&contents))
+}
+
+fn sip_hash(data: &[u8]) -> u128 {
+    let mut hasher = SipHasher13::new();
+    hasher.write(data);
+    hasher.finish128().as_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("manifest-test-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn is_unchanged_false_for_unknown_key() {
+        let manifest = Manifest::default();
+        let path = scratch_file("unknown.bin", b"hello");
+        assert!(!manifest.is_unchanged(&path, "unknown"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn is_unchanged_true_when_content_matches() {
+        let path = scratch_file("unchanged.bin", b"hello world");
+        let mut manifest = Manifest::default();
+        manifest.record(&path, "key");
+        assert!(manifest.is_unchanged(&path, "key"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn is_unchanged_false_when_content_changes() {
+        let path = scratch_file("changed.bin", b"hello world");
+        let mut manifest = Manifest::default();
+        manifest.record(&path, "key");
+        // Same length as the original content so the cheap size check alone
+        // can't catch this: the changed bytes lie in the leading block, so
+        // the partial hash does.
+        fs::write(&path, b"HELLO WORLD").unwrap();
+        assert!(!manifest.is_unchanged(&path, "key"));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+//! EXIF orientation, ICC profile, and metadata passthrough.
+//!
+//! Reads what the source had, applies orientation to the pixel data
+//! before encoding, and, when asked, re-embeds the ICC profile and EXIF
+//! into the WebP container via libwebp's mux API.
+
+use clap::ValueEnum;
+use image::DynamicImage;
+use std::fs;
+use std::path::Path;
+
+/// How much source metadata to carry over into the WebP output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetadataMode {
+    /// Write no metadata at all.
+    Strip,
+    /// Bake EXIF orientation into the pixel data; write nothing else.
+    Orientation,
+    /// Bake orientation in and re-embed the ICC profile and EXIF via
+    /// libwebp's mux API.
+    All,
+}
+
+/// Metadata read from the source image ahead of encoding.
+pub struct SourceMetadata {
+    pub orientation: u32,
+    pub icc_profile: Option<Vec<u8>>,
+    pub exif: Option<Vec<u8>>,
+}
+
+/// Read EXIF orientation, ICC profile, and raw EXIF bytes from `path`.
+/// Missing or unreadable EXIF is not an error: it just means there is
+/// nothing to apply or embed.
+pub fn read_source_metadata(path: &Path) -> SourceMetadata {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => {
+            return SourceMetadata {
+                orientation: 1,
+                icc_profile: None,
+                exif: None,
+            }
+        }
+    };
+    let mut reader = std::io::BufReader::new(file);
+
+    let exif_reader = exif::Reader::new();
+    let exif_data = exif_reader.read_from_container(&mut reader).ok();
+
+    let orientation = exif_data
+        .as_ref()
+        .and_then(|e| e.get_field(exif::Tag::Orientation, exif::In::PRIMARY))
+        .and_then(|f| f.value.get_uint(0))
+        .unwrap_or(1);
+
+    let exif = exif_data.as_ref().map(|e| e.buf().to_vec());
+    let icc_profile = read_icc_profile(path);
+
+    SourceMetadata {
+        orientation,
+        icc_profile,
+        exif,
+    }
+}
+
+/// Read an embedded ICC profile from a JPEG/PNG container, if present.
+fn read_icc_profile(path: &Path) -> Option<Vec<u8>> {
+    let bytes = fs::read(path).ok()?;
+    let ext = path.extension().and_then(|e| e.to_str())?.to_lowercase();
+
+    use img_parts::ImageICC;
+
+    if ext == "jpg" || ext == "jpeg" {
+        img_parts::jpeg::Jpeg::from_bytes(bytes.into())
+            .ok()?
+            .icc_profile()
+            .map(|b| b.to_vec())
+    } else if ext == "png" {
+        img_parts::png::Png::from_bytes(bytes.into())
+            .ok()?
+            .icc_profile()
+            .map(|b| b.to_vec())
+    } else {
+        None
+    }
+}
+
+/// Apply the EXIF orientation tag (values 1-8, per the EXIF spec) to `img`
+/// so the pixel data itself is upright.
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Re-embed the ICC profile and/or EXIF payload into an already-encoded
+/// WebP buffer via libwebp's mux API. Returns the input unchanged if there
+/// is nothing to embed.
+pub fn mux_metadata(webp_data: Vec<u8>, metadata: &SourceMetadata) -> Result<Vec<u8>, String> {
+    if metadata.icc_profile.is_none() && metadata.exif.is_none() {
+        return Ok(webp_data);
+    }
+
+    use libwebp_sys::{
+        WebPDataClear, WebPMuxAssemble, WebPMuxCreateInternal, WebPMuxDelete, WebPMuxSetChunk,
+        WebPData, WEBP_MUX_ABI_VERSION,
+    };
+
+    unsafe {
+        let input = WebPData {
+            bytes: webp_data.as_ptr(),
+            size: webp_data.len(),
+        };
+        // `WebPMuxCreate` is a convenience macro in the C headers, not an
+        // exported symbol; libwebp-sys only binds the underlying
+        // `WebPMuxCreateInternal`, so call that directly with the ABI
+        // version it would otherwise have supplied. `copy_data = 1` makes
+        // the mux own its own copy, so `webp_data` can be dropped safely.
+        let mux = WebPMuxCreateInternal(&input, 1, WEBP_MUX_ABI_VERSION as i32);
+        if mux.is_null() {
+            return Err("Failed to create WebP mux".to_string());
+        }
+
+        let iccp_fourcc = std::ffi::CString::new("ICCP").unwrap();
+        let exif_fourcc = std::ffi::CString::new("EXIF").unwrap();
+
+        if let Some(icc) = &metadata.icc_profile {
+            let chunk = WebPData {
+                bytes: icc.as_ptr(),
+                size: icc.len(),
+            };
+            WebPMuxSetChunk(mux, iccp_fourcc.as_ptr(), &chunk, 1);
+        }
+
+        if let Some(exif) = &metadata.exif {
+            let chunk = WebPData {
+                bytes: exif.as_ptr(),
+                size: exif.len(),
+            };
+            WebPMuxSetChunk(mux, exif_fourcc.as_ptr(), &chunk, 1);
+        }
+
+        let mut output = WebPData {
+            bytes: std::ptr::null(),
+            size: 0,
+        };
+        let result = WebPMuxAssemble(mux, &mut output);
+        WebPMuxDelete(mux);
+
+        if result != libwebp_sys::WebPMuxError::WEBP_MUX_OK {
+            WebPDataClear(&mut output);
+            return Err(format!("Failed to assemble WebP mux: {:?}", result));
+        }
+
+        let assembled = std::slice::from_raw_parts(output.bytes, output.size).to_vec();
+        // `output.bytes` was heap-allocated by libwebp's own allocator; it
+        // must be freed through libwebp, not left for Rust to leak.
+        WebPDataClear(&mut output);
+        Ok(assembled)
+    }
+}
@@ -0,0 +1,43 @@
+//! Machine-readable JSON report for the whole run.
+//!
+//! stdout is for humans watching a terminal; `--report` is for CI jobs and
+//! web backends that need to consume a run's outcome without scraping
+//! progress lines.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize)]
+pub struct ConversionReport {
+    pub input: String,
+    pub output: String,
+    pub success: bool,
+    pub message: String,
+    pub original_size: u64,
+    pub new_size: u64,
+    pub savings_percent: f64,
+    pub duration_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub total_files: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub original_size: u64,
+    pub new_size: u64,
+    pub total_savings_percent: f64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub summary: Summary,
+    pub conversions: Vec<ConversionReport>,
+}
+
+pub fn write_report(path: &Path, report: &Report) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}